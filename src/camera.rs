@@ -0,0 +1,163 @@
+// camera.rs
+//
+// A free-flying yaw/pitch camera driven by `CameraController`, replacing the
+// fixed-eye automatic orbit. `CameraController` only accumulates state from
+// input events; `update_camera` integrates it by `dt` so movement speed is
+// framerate-independent.
+
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+
+use winit::event::{ ElementState, MouseScrollDelta };
+use winit::keyboard::KeyCode;
+
+const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
+
+pub struct Camera {
+    pub position: glam::Vec3,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Camera {
+    pub fn new(position: glam::Vec3, yaw: f32, pitch: f32) -> Self {
+        Self { position, yaw, pitch }
+    }
+
+    pub fn calc_matrix(&self) -> glam::Mat4 {
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+
+        let forward = glam::Vec3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw).normalize();
+
+        glam::Mat4::look_to_rh(self.position, forward, glam::Vec3::Y)
+    }
+}
+
+pub struct Projection {
+    aspect: f32,
+    fovy: f32,
+    znear: f32,
+    zfar: f32,
+}
+
+impl Projection {
+    pub fn new(width: u32, height: u32, fovy: f32, znear: f32, zfar: f32) -> Self {
+        Self {
+            aspect: (width as f32) / (height as f32),
+            fovy,
+            znear,
+            zfar,
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.aspect = (width as f32) / (height as f32);
+    }
+
+    pub fn calc_matrix(&self) -> glam::Mat4 {
+        glam::Mat4::perspective_rh(self.fovy, self.aspect, self.znear, self.zfar)
+    }
+}
+
+#[derive(Default)]
+pub struct CameraController {
+    amount_left: f32,
+    amount_right: f32,
+    amount_forward: f32,
+    amount_backward: f32,
+    amount_up: f32,
+    amount_down: f32,
+    rotate_horizontal: f32,
+    rotate_vertical: f32,
+    scroll: f32,
+    speed: f32,
+    sensitivity: f32,
+}
+
+impl CameraController {
+    pub fn new(speed: f32, sensitivity: f32) -> Self {
+        Self {
+            speed,
+            sensitivity,
+            ..Default::default()
+        }
+    }
+
+    /// Accumulates WASD/space/shift state. Returns `true` if `key` is one
+    /// this controller understands, so `State::input` can report the event
+    /// as consumed.
+    pub fn process_keyboard(&mut self, key: KeyCode, state: ElementState) -> bool {
+        let amount = if state == ElementState::Pressed { 1.0 } else { 0.0 };
+        match key {
+            KeyCode::KeyW => {
+                self.amount_forward = amount;
+                true
+            }
+            KeyCode::KeyS => {
+                self.amount_backward = amount;
+                true
+            }
+            KeyCode::KeyA => {
+                self.amount_left = amount;
+                true
+            }
+            KeyCode::KeyD => {
+                self.amount_right = amount;
+                true
+            }
+            KeyCode::Space => {
+                self.amount_up = amount;
+                true
+            }
+            KeyCode::ShiftLeft => {
+                self.amount_down = amount;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
+        self.rotate_horizontal += mouse_dx as f32;
+        self.rotate_vertical += mouse_dy as f32;
+    }
+
+    pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
+        self.scroll += match delta {
+            MouseScrollDelta::LineDelta(_, scroll) => -scroll * 0.5,
+            MouseScrollDelta::PixelDelta(pos) => -(pos.y as f32),
+        };
+    }
+
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: Duration) {
+        let dt = dt.as_secs_f32();
+
+        // Move forward/back and left/right along the camera's own facing
+        // direction, ignoring pitch so the camera doesn't dive when looking
+        // up or down.
+        let (yaw_sin, yaw_cos) = camera.yaw.sin_cos();
+        let forward = glam::Vec3::new(yaw_cos, 0.0, yaw_sin).normalize();
+        let right = glam::Vec3::new(-yaw_sin, 0.0, yaw_cos).normalize();
+        camera.position += forward * (self.amount_forward - self.amount_backward) * self.speed * dt;
+        camera.position += right * (self.amount_right - self.amount_left) * self.speed * dt;
+
+        // Zoom in and out by walking along the forward vector.
+        let (pitch_sin, pitch_cos) = camera.pitch.sin_cos();
+        let scrollward = glam::Vec3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalize();
+        camera.position += scrollward * self.scroll * self.speed * self.sensitivity * dt;
+        self.scroll = 0.0;
+
+        // Move up/down independent of facing direction.
+        camera.position.y += (self.amount_up - self.amount_down) * self.speed * dt;
+
+        // Rotate from mouse motion.
+        camera.yaw += self.rotate_horizontal * self.sensitivity * dt;
+        camera.pitch += -self.rotate_vertical * self.sensitivity * dt;
+        self.rotate_horizontal = 0.0;
+        self.rotate_vertical = 0.0;
+
+        // Clamp pitch so the camera can't flip over.
+        camera.pitch = camera.pitch.clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2);
+    }
+}
@@ -0,0 +1,85 @@
+// instance.rs
+//
+// Per-instance data for hardware instancing, modeled on the learn-wgpu
+// instancing tutorial: `Instance` is the CPU-side representation, while
+// `InstanceRaw` is the packed form uploaded into the instance vertex buffer.
+
+/// A single instance's placement in world space.
+pub struct Instance {
+    pub position: glam::Vec3,
+    pub rotation: glam::Quat,
+}
+
+impl Instance {
+    pub fn to_raw(&self) -> InstanceRaw {
+        let model = glam::Mat4::from_translation(self.position) * glam::Mat4::from_quat(self.rotation);
+        // Normal matrix = inverse-transpose of the model's upper 3x3, so
+        // normals stay correct under non-uniform scale/rotation.
+        let normal_matrix = glam::Mat3::from_mat4(model).inverse().transpose();
+
+        InstanceRaw {
+            model: model.to_cols_array_2d(),
+            normal: normal_matrix.to_cols_array_2d(),
+        }
+    }
+}
+
+/// Packed, `Pod`-safe form of `Instance` suitable for a `wgpu::Buffer`.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    model: [[f32; 4]; 4],
+    normal: [[f32; 3]; 3],
+}
+
+impl InstanceRaw {
+    /// Vertex buffer layout exposing the model matrix as four `Float32x4`
+    /// attributes at shader locations 5-8 (reassembled into a `mat4x4<f32>`)
+    /// and the normal matrix as three `Float32x3` attributes at locations
+    /// 9-11 (reassembled into a `mat3x3<f32>`) in the vertex shader.
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        use std::mem;
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 19]>() as wgpu::BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 22]>() as wgpu::BufferAddress,
+                    shader_location: 11,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
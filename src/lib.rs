@@ -11,159 +11,223 @@ use winit::{
     window::{ Window, WindowBuilder },
 };
 
-// Image processing import
-// use image::GenericImageView;
-
 use wgpu::util::DeviceExt;
 
+mod instance;
+use instance::{ Instance, InstanceRaw };
+
+mod texture;
+
+mod model;
+use model::{ DrawModel, Model, ModelVertex, Vertex };
+
+mod light;
+use light::LightUniform;
+
+mod camera;
+use camera::{ Camera, CameraController, Projection };
+
+mod texture_target;
+use texture_target::TextureTarget;
+
 // Import for WebAssembly (wasm32) target, if applicable
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+// Instances are laid out on a flat NUM_INSTANCES_PER_ROW x NUM_INSTANCES_PER_ROW
+// grid, displaced so the grid is centered on the origin.
+const NUM_INSTANCES_PER_ROW: u32 = 25;
+const INSTANCE_SPACING: f32 = 1.5;
+const INSTANCE_DISPLACEMENT: glam::Vec3 = glam::Vec3::new(
+    ((NUM_INSTANCES_PER_ROW - 1) as f32) * INSTANCE_SPACING * 0.5,
+    0.0,
+    ((NUM_INSTANCES_PER_ROW - 1) as f32) * INSTANCE_SPACING * 0.5,
+);
+
+// Format for the depth buffer, exposed so future passes (shadow maps,
+// outlines) can reuse it without guessing what the main pass picked.
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// Creates a depth texture (and its view) sized to the surface configuration.
+// `sample_count` must match the color attachment it's paired with.
+fn create_depth_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let size = wgpu::Extent3d {
+        width: config.width.max(1),
+        height: config.height.max(1),
+        depth_or_array_layers: 1,
+    };
+
+    let texture = device.create_texture(
+        &(wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+    );
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    (texture, view)
+}
+
+// Picks the highest MSAA sample count the adapter supports for `format`,
+// following Ruffle's `preferred_sample_count` approach: try 4x first, then
+// 2x, and fall back to no multisampling if neither is supported.
+fn preferred_sample_count(adapter: &wgpu::Adapter, format: wgpu::TextureFormat) -> u32 {
+    let flags = adapter.get_texture_format_features(format).flags;
+
+    [4, 2, 1]
+        .into_iter()
+        .find(|&count| flags.sample_count_supported(count))
+        .unwrap_or(1)
+}
+
+// Creates the multisampled color attachment rendered into each frame before
+// being resolved into the (non-multisampled) swapchain texture.
+fn create_msaa_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32) -> wgpu::TextureView {
+    let size = wgpu::Extent3d {
+        width: config.width.max(1),
+        height: config.height.max(1),
+        depth_or_array_layers: 1,
+    };
+
+    let texture = device.create_texture(
+        &(wgpu::TextureDescriptor {
+            label: Some("MSAA Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+    );
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Packed form of the `CameraUniform` struct in `shader.wgsl`. Carries the
+/// view position alongside view-projection so the fragment shader can build
+/// a specular half-vector without a second binding.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    view_position: [f32; 4],
+    view_proj: [[f32; 4]; 4],
+}
+
+// Where a frame ends up: presented to an open window, or rendered into an
+// offscreen texture and read back on the CPU. `State` otherwise doesn't care
+// which one it has — `render()` branches on this instead of requiring a
+// surface everywhere.
+enum RenderTarget<'a> {
+    Window {
+        surface: wgpu::Surface<'a>,
+        window: &'a Window,
+    },
+    Texture(TextureTarget),
+}
+
 // The main state struct which holds all resources needed for rendering
 struct State<'a> {
-    surface: wgpu::Surface<'a>, // Surface that represents the part of the window where rendering occurs
+    target: RenderTarget<'a>, // Where frames are rendered to: a window surface, or an offscreen texture
     device: wgpu::Device, // Represents the GPU and handles resource management
     queue: wgpu::Queue, // Handles the submission of commands to the GPU
     config: wgpu::SurfaceConfiguration, // Configuration for the surface, including display format and resolution
     size: winit::dpi::PhysicalSize<u32>, // Window size in physical pixels
-    window: &'a Window, // Reference to the window instance for rendering
     render_pipeline: wgpu::RenderPipeline, // The pipeline object that contains rendering configurations
     uniform_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
-    rotation_angle_x: f32,
-    rotation_angle_y: f32,
+    light_uniform: LightUniform,
+    light_buffer: wgpu::Buffer,
+    light_bind_group: wgpu::BindGroup,
+    obj_model: Model,
+    instances: Vec<Instance>,
+    instance_buffer: wgpu::Buffer,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    sample_count: u32,
+    msaa_view: Option<wgpu::TextureView>,
+    camera: Camera,
+    projection: Projection,
+    camera_controller: CameraController,
+    last_render_time: std::time::Instant,
 }
 
-// Implementation of the State struct
-impl<'a> State<'a> {
-    // Asynchronous method to initialize a new State instance
-    async fn new(window: &'a Window) -> State<'a> {
-        let size = window.inner_size(); // Get the initial window size
-
-        // Create an instance for interfacing with the GPU
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            #[cfg(not(target_arch = "wasm32"))]
-            backends: wgpu::Backends::PRIMARY, // Use primary backend on native platforms
-            #[cfg(target_arch = "wasm32")]
-            backends: wgpu::Backends::GL, // Use OpenGL backend for WebAssembly
-            ..Default::default()
-        });
-
-        // Create a surface for rendering in the window
-        let surface = instance.create_surface(window).unwrap();
+// Everything `State` needs besides a `RenderTarget`: the instance grid,
+// depth/MSAA targets, camera, and the bind groups and pipeline built from
+// them. Shared between the windowed and headless constructors so neither one
+// drifts from the other.
+struct PipelineResources {
+    render_pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    light_uniform: LightUniform,
+    light_buffer: wgpu::Buffer,
+    light_bind_group: wgpu::BindGroup,
+    obj_model: Model,
+    instances: Vec<Instance>,
+    instance_buffer: wgpu::Buffer,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    sample_count: u32,
+    msaa_view: Option<wgpu::TextureView>,
+    camera: Camera,
+    projection: Projection,
+    camera_controller: CameraController,
+}
 
-        // Request a GPU adapter that meets the preferred criteria
-        let adapter = instance
-            .request_adapter(
-                &(wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance, // Prefer high-performance GPU
-                    compatible_surface: Some(&surface), // Ensure adapter is compatible with the surface
-                    force_fallback_adapter: false, // Do not force a fallback adapter
+// Builds everything in `PipelineResources` from a configured device/queue and
+// the surface (or offscreen) configuration they'll render into.
+async fn build_pipeline_resources(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    adapter: &wgpu::Adapter,
+    config: &wgpu::SurfaceConfiguration
+) -> PipelineResources {
+    // Build the instance grid: NUM_INSTANCES_PER_ROW x NUM_INSTANCES_PER_ROW
+        // copies of the model laid out on the XZ plane, displaced so the grid centers on
+        // the origin.
+        let instances = (0..NUM_INSTANCES_PER_ROW)
+            .flat_map(|z| {
+                (0..NUM_INSTANCES_PER_ROW).map(move |x| {
+                    let position =
+                        glam::Vec3::new((x as f32) * INSTANCE_SPACING, 0.0, (z as f32) * INSTANCE_SPACING) -
+                        INSTANCE_DISPLACEMENT;
+                    let rotation = glam::Quat::IDENTITY;
+                    Instance { position, rotation }
                 })
-            ).await
-            .expect("Failed to find a compatible GPU adapter");
-
-        // Request a logical device and a command queue from the adapter
-        let (device, queue) = adapter
-            .request_device(
-                &(wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: wgpu::MemoryHints::default(),
-                }),
-                None
-            ).await
-            .unwrap();
-
-        // Get the supported formats and modes for the surface
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps.formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb()) // Prefer sRGB format for better color accuracy
-            .unwrap_or(surface_caps.formats[0]);
-
-        // Configure the surface with specified usage and format
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT, // Usage for render output
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2, // Or Some(value)
-        };
-
-        // Configure the surface with device and configuration
-        surface.configure(&device, &config);
-
-        // ** NEW CODE STARTS HERE **
-
-        // Import image crate for loading PNG files
-        use image::GenericImageView; // Add this import at the top of your file
-
-        // Load the image
-        let img = image::open("assets/scenary.png").expect("Failed to load texture");
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
-
-        // Create the texture
-        let texture_size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
-        };
+            })
+            .collect::<Vec<_>>();
 
-        let texture = device.create_texture(
-            &(wgpu::TextureDescriptor {
-                label: Some("Texture"),
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                view_formats: &[],
+        let instance_data = instances.iter().map(Instance::to_raw).collect::<Vec<_>>();
+        let instance_buffer = device.create_buffer_init(
+            &(wgpu::util::BufferInitDescriptor {
+                label: Some("Instance Buffer"),
+                contents: bytemuck::cast_slice(&instance_data),
+                usage: wgpu::BufferUsages::VERTEX,
             })
         );
 
-        // Copy the image data into the texture
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: None,
-            },
-            texture_size
-        );
+        let sample_count = preferred_sample_count(adapter, config.format);
+        let msaa_view = (sample_count > 1).then(|| create_msaa_texture(device, config, sample_count));
 
-        // Create a texture view
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        // Create a sampler
-        let sampler = device.create_sampler(
-            &(wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
-                mag_filter: wgpu::FilterMode::Linear,
-                min_filter: wgpu::FilterMode::Linear,
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                ..Default::default()
-            })
-        );
+        let (depth_texture, depth_view) = create_depth_texture(device, config, sample_count);
 
-        // ** NEW CODE ENDS HERE **
+        // Camera: starts back and slightly above the instance grid, looking
+        // down and toward the origin.
+        let camera = Camera::new(glam::Vec3::new(0.0, 10.0, 30.0), -std::f32::consts::FRAC_PI_2, -0.3);
+        let projection = Projection::new(config.width, config.height, 45.0f32.to_radians(), 0.1, 1000.0);
+        let camera_controller = CameraController::new(20.0, 1.0);
 
         // Load the WGSL shader code from an external file
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -171,44 +235,70 @@ impl<'a> State<'a> {
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });
 
-        // let transform_matrix = glam::Mat4::IDENTITY.to_cols_array();
-        // let uniform_buffer = device.create_buffer_init(
-        //     &(wgpu::util::BufferInitDescriptor {
-        //         label: Some("Uniform Buffer"),
-        //         contents: bytemuck::cast_slice(&transform_matrix),
-        //         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        //     })
-        // );
-
-        let transform_matrix = [0.0f32; 16]; // 4x4 matrix
+        let camera_uniform = CameraUniform {
+            view_position: [0.0; 4],
+            view_proj: glam::Mat4::IDENTITY.to_cols_array_2d(),
+        };
         let uniform_buffer = device.create_buffer_init(
             &(wgpu::util::BufferInitDescriptor {
                 label: Some("Uniform Buffer"),
-                contents: bytemuck::cast_slice(&transform_matrix),
+                contents: bytemuck::cast_slice(&[camera_uniform]),
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             })
         );
 
-        // ** UPDATED BIND GROUP LAYOUT **
+        // Light: a single movable point light, orbited in `update()`.
+        let light_uniform = LightUniform {
+            position: [8.0, 8.0, 8.0],
+            _padding: 0,
+            color: [1.0, 1.0, 1.0],
+            _padding2: 0,
+        };
+        let light_buffer = device.create_buffer_init(
+            &(wgpu::util::BufferInitDescriptor {
+                label: Some("Light Buffer"),
+                contents: bytemuck::cast_slice(&[light_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            })
+        );
 
+        // Camera bind group: the view-projection matrix and camera position. Per-material
+        // textures live in their own bind group (group 1) so a multi-mesh
+        // model can swap textures between draw calls without touching the
+        // camera binding.
         let bind_group_layout = device.create_bind_group_layout(
             &(wgpu::BindGroupLayoutDescriptor {
-                label: Some("Bind Group Layout"),
+                label: Some("Camera Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            })
+        );
+
+        let bind_group = device.create_bind_group(
+            &(wgpu::BindGroupDescriptor {
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                }],
+                label: Some("Camera Bind Group"),
+            })
+        );
+
+        let texture_bind_group_layout = device.create_bind_group_layout(
+            &(wgpu::BindGroupLayoutDescriptor {
+                label: Some("Texture Bind Group Layout"),
                 entries: &[
-                    // Transformation matrix
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    // Texture binding
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Texture {
                             multisampled: false,
@@ -217,9 +307,8 @@ impl<'a> State<'a> {
                         },
                         count: None,
                     },
-                    // Sampler binding
                     wgpu::BindGroupLayoutEntry {
-                        binding: 2,
+                        binding: 1,
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                         count: None,
@@ -228,26 +317,34 @@ impl<'a> State<'a> {
             })
         );
 
-        // ** UPDATED BIND GROUP **
+        // Load the model's meshes and per-material textures in place of the
+        // old hardcoded quad.
+        let obj_model = model::Model::load(device, queue, &texture_bind_group_layout, "assets/model.obj");
 
-        let bind_group = device.create_bind_group(
-            &(wgpu::BindGroupDescriptor {
-                layout: &bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: uniform_buffer.as_entire_binding(),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&texture_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: wgpu::BindingResource::Sampler(&sampler),
+        let light_bind_group_layout = device.create_bind_group_layout(
+            &(wgpu::BindGroupLayoutDescriptor {
+                label: Some("Light Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
                     },
-                ],
-                label: Some("Bind Group"),
+                    count: None,
+                }],
+            })
+        );
+
+        let light_bind_group = device.create_bind_group(
+            &(wgpu::BindGroupDescriptor {
+                layout: &light_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: light_buffer.as_entire_binding(),
+                }],
+                label: Some("Light Bind Group"),
             })
         );
 
@@ -255,7 +352,7 @@ impl<'a> State<'a> {
         let render_pipeline_layout = device.create_pipeline_layout(
             &(wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&bind_group_layout],
+                bind_group_layouts: &[&bind_group_layout, &texture_bind_group_layout, &light_bind_group_layout],
                 push_constant_ranges: &[],
             })
         );
@@ -268,7 +365,7 @@ impl<'a> State<'a> {
                 vertex: wgpu::VertexState {
                     module: &shader,
                     entry_point: "vs_main",
-                    buffers: &[],
+                    buffers: &[ModelVertex::desc(), InstanceRaw::desc()],
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
                 },
                 fragment: Some(wgpu::FragmentState {
@@ -292,9 +389,15 @@ impl<'a> State<'a> {
                     unclipped_depth: false,
                     conservative: false,
                 },
-                depth_stencil: None,
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
                 multisample: wgpu::MultisampleState {
-                    count: 1,
+                    count: sample_count,
                     mask: !0,
                     alpha_to_coverage_enabled: false,
                 },
@@ -303,25 +406,205 @@ impl<'a> State<'a> {
             })
         );
 
+        // Hand everything back to the caller, which owns the target
+        // (surface or offscreen texture) this was built for.
+        PipelineResources {
+            render_pipeline,
+            uniform_buffer,
+            bind_group,
+            light_uniform,
+            light_buffer,
+            light_bind_group,
+            obj_model,
+            instances,
+            instance_buffer,
+            depth_texture,
+            depth_view,
+            sample_count,
+            msaa_view,
+            camera,
+            projection,
+            camera_controller,
+        }
+}
+
+// Implementation of the State struct
+impl<'a> State<'a> {
+    // Asynchronous method to initialize a new State instance rendering into
+    // a window's surface.
+    async fn new(window: &'a Window) -> State<'a> {
+        let size = window.inner_size(); // Get the initial window size
+
+        // Create an instance for interfacing with the GPU
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            #[cfg(not(target_arch = "wasm32"))]
+            backends: wgpu::Backends::PRIMARY, // Use primary backend on native platforms
+            #[cfg(target_arch = "wasm32")]
+            backends: wgpu::Backends::GL, // Use OpenGL backend for WebAssembly
+            ..Default::default()
+        });
+
+        // Create a surface for rendering in the window
+        let surface = instance.create_surface(window).unwrap();
+
+        // Request a GPU adapter that meets the preferred criteria
+        let adapter = instance
+            .request_adapter(
+                &(wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance, // Prefer high-performance GPU
+                    compatible_surface: Some(&surface), // Ensure adapter is compatible with the surface
+                    force_fallback_adapter: false, // Do not force a fallback adapter
+                })
+            ).await
+            .expect("Failed to find a compatible GPU adapter");
+
+        // Request a logical device and a command queue from the adapter
+        let (device, queue) = adapter
+            .request_device(
+                &(wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::default(),
+                    memory_hints: wgpu::MemoryHints::default(),
+                }),
+                None
+            ).await
+            .unwrap();
+
+        // Get the supported formats and modes for the surface
+        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_format = surface_caps.formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb()) // Prefer sRGB format for better color accuracy
+            .unwrap_or(surface_caps.formats[0]);
+
+        // Configure the surface with specified usage and format
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT, // Usage for render output
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2, // Or Some(value)
+        };
+
+        // Configure the surface with device and configuration
+        surface.configure(&device, &config);
+
+        let resources = build_pipeline_resources(&device, &queue, &adapter, &config).await;
+
         // Return the initialized State
         Self {
-            surface,
+            target: RenderTarget::Window { surface, window },
             device,
             queue,
             config,
             size,
-            window,
-            render_pipeline,
-            uniform_buffer,
-            bind_group,
-            rotation_angle_x: 0.0,
-            rotation_angle_y: 0.0,
+            render_pipeline: resources.render_pipeline,
+            uniform_buffer: resources.uniform_buffer,
+            bind_group: resources.bind_group,
+            light_uniform: resources.light_uniform,
+            light_buffer: resources.light_buffer,
+            light_bind_group: resources.light_bind_group,
+            obj_model: resources.obj_model,
+            instances: resources.instances,
+            instance_buffer: resources.instance_buffer,
+            depth_texture: resources.depth_texture,
+            depth_view: resources.depth_view,
+            sample_count: resources.sample_count,
+            msaa_view: resources.msaa_view,
+            camera: resources.camera,
+            projection: resources.projection,
+            camera_controller: resources.camera_controller,
+            last_render_time: std::time::Instant::now(),
         }
     }
 
-    // Accessor for the window reference
+    // Asynchronous method to initialize a new State instance rendering into
+    // an offscreen `TextureTarget` instead of a window surface, for use on
+    // servers or in CI with no display. Mirrors `new()`, but there's no
+    // window to request a surface format or present_mode from, so the
+    // offscreen texture format and dimensions are chosen up front instead.
+    async fn new_headless(width: u32, height: u32) -> State<'static> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::PRIMARY,
+            ..Default::default()
+        });
+
+        let adapter = instance
+            .request_adapter(
+                &(wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    compatible_surface: None, // No window surface to be compatible with
+                    force_fallback_adapter: false,
+                })
+            ).await
+            .expect("Failed to find a compatible GPU adapter");
+
+        let (device, queue) = adapter
+            .request_device(
+                &(wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits::default(),
+                    memory_hints: wgpu::MemoryHints::default(),
+                }),
+                None
+            ).await
+            .unwrap();
+
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        let texture_target = TextureTarget::new(&device, width, height, format);
+
+        let resources = build_pipeline_resources(&device, &queue, &adapter, &config).await;
+
+        Self {
+            target: RenderTarget::Texture(texture_target),
+            device,
+            queue,
+            config,
+            size: winit::dpi::PhysicalSize::new(width, height),
+            render_pipeline: resources.render_pipeline,
+            uniform_buffer: resources.uniform_buffer,
+            bind_group: resources.bind_group,
+            light_uniform: resources.light_uniform,
+            light_buffer: resources.light_buffer,
+            light_bind_group: resources.light_bind_group,
+            obj_model: resources.obj_model,
+            instances: resources.instances,
+            instance_buffer: resources.instance_buffer,
+            depth_texture: resources.depth_texture,
+            depth_view: resources.depth_view,
+            sample_count: resources.sample_count,
+            msaa_view: resources.msaa_view,
+            camera: resources.camera,
+            projection: resources.projection,
+            camera_controller: resources.camera_controller,
+            last_render_time: std::time::Instant::now(),
+        }
+    }
+
+    // Accessor for the window reference. Only valid on a `State` built with
+    // `new()`; headless state has no window to return.
     fn window(&self) -> &Window {
-        &self.window
+        match &self.target {
+            RenderTarget::Window { window, .. } => window,
+            RenderTarget::Texture(_) => panic!("window() called on a headless State"),
+        }
     }
 
     // Resize handler to update surface configuration if the window size changes
@@ -330,62 +613,68 @@ impl<'a> State<'a> {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+
+            if let RenderTarget::Window { surface, .. } = &self.target {
+                surface.configure(&self.device, &self.config);
+            }
+
+            self.projection.resize(new_size.width, new_size.height);
+
+            let (depth_texture, depth_view) = create_depth_texture(&self.device, &self.config, self.sample_count);
+            self.depth_texture = depth_texture;
+            self.depth_view = depth_view;
+
+            if self.sample_count > 1 {
+                self.msaa_view = Some(create_msaa_texture(&self.device, &self.config, self.sample_count));
+            }
         }
     }
 
-    // Handles input events, returning false as no input handling is done in this example
-    #[allow(unused_variables)]
+    // Forwards WASD/space/shift key state to the camera controller. Returns
+    // true when the event was a recognized movement key, so the event loop
+    // doesn't also treat it as, e.g., the close-on-Escape shortcut.
     fn input(&mut self, event: &WindowEvent) -> bool {
-        false
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent { physical_key: PhysicalKey::Code(key), state, .. },
+                ..
+            } => self.camera_controller.process_keyboard(*key, *state),
+            _ => false,
+        }
     }
 
-    // Update function to rotate the square in 3D space
-    fn update(&mut self) {
-        // Update rotation angles with different speeds
-        self.rotation_angle_x += 0.0001; // Speed for X-axis rotation
-        self.rotation_angle_y += 0.0003; // Speed for Y-axis rotation
-    
-        // Create rotation matrices
-        let rotation_x = glam::Mat4::from_rotation_x(self.rotation_angle_x);
-        let rotation_y = glam::Mat4::from_rotation_y(self.rotation_angle_y);
-    
-        // Combine rotations
-        let model = rotation_y * rotation_x;
-    
-        // View matrix
-        let eye = glam::Vec3::new(0.0, 0.0, 2.0);
-        let center = glam::Vec3::ZERO;
-        let up = glam::Vec3::Y;
-        let view = glam::Mat4::look_at_rh(eye, center, up);
-    
-        // Projection matrix
-        let aspect_ratio = self.size.width as f32 / self.size.height as f32;
-        let fovy = 45.0f32.to_radians();
-        let projection = glam::Mat4::perspective_rh(fovy, aspect_ratio, 0.1, 100.0);
-    
-        // MVP matrix
-        let mvp = projection * view * model;
-    
-        // Update the uniform buffer
-        self.queue.write_buffer(
-            &self.uniform_buffer,
-            0,
-            bytemuck::cast_slice(&mvp.to_cols_array()),
-        );
+    // Update function: integrates camera movement by `dt` and rebuilds the
+    // view-projection matrix written to the uniform buffer. Per-instance
+    // model matrices live in the instance buffer, so the uniform buffer
+    // only ever holds the camera's view-projection and position.
+    fn update(&mut self, dt: std::time::Duration) {
+        self.camera_controller.update_camera(&mut self.camera, dt);
+
+        let view_proj = self.projection.calc_matrix() * self.camera.calc_matrix();
+        let camera_uniform = CameraUniform {
+            view_position: [self.camera.position.x, self.camera.position.y, self.camera.position.z, 1.0],
+            view_proj: view_proj.to_cols_array_2d(),
+        };
+        self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
+
+        // Orbit the light around the origin so the Blinn-Phong shading is
+        // visibly dynamic.
+        let old_position = glam::Vec3::from(self.light_uniform.position);
+        let new_position = glam::Quat::from_axis_angle(glam::Vec3::Y, dt.as_secs_f32()) * old_position;
+        self.light_uniform.position = new_position.into();
+        self.queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[self.light_uniform]));
     }
-    
 
-    // Render function that performs the drawing operations
-    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?; // Get the next texture for rendering
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default()); // Create a view for the texture
 
-        let mut encoder = self.device.create_command_encoder(
-            &(wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            })
-        );
+    // Records the render pass into `encoder`, drawing into `color_target`
+    // (or, with MSAA enabled, into the multisampled texture that resolves
+    // into `color_target`). Shared by both the windowed and offscreen render
+    // paths below, since neither cares where `color_target` ends up.
+    fn encode_draw(&self, encoder: &mut wgpu::CommandEncoder, color_target: &wgpu::TextureView) {
+        let (color_view, resolve_target) = match &self.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(color_target)),
+            None => (color_target, None),
+        };
 
         // Start the render pass
         let mut render_pass = encoder.begin_render_pass(
@@ -393,8 +682,8 @@ impl<'a> State<'a> {
                 label: Some("Render Pass"),
                 color_attachments: &[
                     Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
+                        view: color_view,
+                        resolve_target,
                         ops: wgpu::Operations {
                             load: wgpu::LoadOp::Clear(wgpu::Color {
                                 r: 0.02,
@@ -406,23 +695,92 @@ impl<'a> State<'a> {
                         },
                     }),
                 ],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             })
         );
 
         render_pass.set_pipeline(&self.render_pipeline); // Set the render pipeline
-        render_pass.set_bind_group(0, &self.bind_group, &[]); // Bind the uniform buffer
-        render_pass.draw(0..6, 0..1); // Draw 6 vertices for two triangles
-
-        drop(render_pass); // End the render pass
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..)); // Bind per-instance model matrices
+        render_pass.draw_model(&self.obj_model, &self.bind_group, &self.light_bind_group, 0..(self.instances.len() as u32));
+    }
 
-        self.queue.submit(iter::once(encoder.finish())); // Submit the command buffer for execution
-        output.present(); // Present the rendered image to the window
+    // Render function that performs the drawing operations. Draws into the
+    // window surface, or into the offscreen `TextureTarget` and copies the
+    // result into its readback buffer, depending on how this `State` was
+    // constructed.
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        match &self.target {
+            RenderTarget::Window { surface, .. } => {
+                let output = surface.get_current_texture()?; // Get the next texture for rendering
+                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default()); // Create a view for the texture
+
+                let mut encoder = self.device.create_command_encoder(
+                    &(wgpu::CommandEncoderDescriptor {
+                        label: Some("Render Encoder"),
+                    })
+                );
+                self.encode_draw(&mut encoder, &view);
+
+                self.queue.submit(iter::once(encoder.finish())); // Submit the command buffer for execution
+                output.present(); // Present the rendered image to the window
+            }
+            RenderTarget::Texture(texture_target) => {
+                let mut encoder = self.device.create_command_encoder(
+                    &(wgpu::CommandEncoderDescriptor {
+                        label: Some("Render Encoder"),
+                    })
+                );
+                self.encode_draw(&mut encoder, &texture_target.view);
+                texture_target.copy_to_buffer(&mut encoder);
+
+                self.queue.submit(iter::once(encoder.finish()));
+            }
+        }
 
         Ok(())
     }
+
+    // Reads back the offscreen `TextureTarget` this `State` was constructed
+    // with and writes it to `path` as a PNG. Only valid on a `State` built
+    // with `new_headless()`, and only meaningful after a `render()` call has
+    // copied a frame into the target's readback buffer.
+    async fn save_to_png(&self, path: &str) -> Result<(), image::ImageError> {
+        let RenderTarget::Texture(texture_target) = &self.target else {
+            panic!("save_to_png() called on a windowed State");
+        };
+
+        let rgba = texture_target.read_rgba(&self.device).await;
+        image::save_buffer(
+            path,
+            &rgba,
+            texture_target.size.width,
+            texture_target.size.height,
+            image::ColorType::Rgba8
+        )
+    }
+}
+
+// Renders a single frame headlessly and writes it to `path` as a PNG, so the
+// crate can produce frames in CI or on servers with no display. Builds and
+// tears down its own offscreen `State`; callers that need more than one
+// frame should drive `State::new_headless` directly instead.
+pub async fn render_to_png(path: &str) -> Result<(), image::ImageError> {
+    const WIDTH: u32 = 800;
+    const HEIGHT: u32 = 600;
+
+    let mut state = State::new_headless(WIDTH, HEIGHT).await;
+    state.update(std::time::Duration::from_secs_f32(1.0 / 60.0));
+    state.render().expect("offscreen render never fails with wgpu::SurfaceError");
+    state.save_to_png(path).await
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
@@ -482,7 +840,10 @@ pub async fn run() {
                                 state.resize(*physical_size);
                             }
                             WindowEvent::RedrawRequested => {
-                                state.update(); // This will now be called on each redraw
+                                let now = std::time::Instant::now();
+                                let dt = now - state.last_render_time;
+                                state.last_render_time = now;
+                                state.update(dt); // Real frame time keeps motion framerate-independent
                                 match state.render() {
                                     Ok(_) => {}
                                     Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) =>
@@ -496,6 +857,13 @@ pub async fn run() {
                         }
                     }
                 }
+                Event::DeviceEvent { event, .. } => {
+                    match event {
+                        DeviceEvent::MouseMotion { delta } => state.camera_controller.process_mouse(delta.0, delta.1),
+                        DeviceEvent::MouseWheel { delta } => state.camera_controller.process_scroll(&delta),
+                        _ => {}
+                    }
+                }
                 _ => {}
             }
         })
@@ -0,0 +1,14 @@
+// light.rs
+//
+// A single movable point light. `LightUniform` mirrors the WGSL struct in
+// `shader.wgsl`; the trailing `_padding` fields exist because wgpu requires
+// uniform buffer members to be 16-byte aligned.
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    pub position: [f32; 3],
+    pub _padding: u32,
+    pub color: [f32; 3],
+    pub _padding2: u32,
+}
@@ -0,0 +1,223 @@
+// model.rs
+//
+// Interleaved vertex/index geometry loaded from OBJ+MTL pairs via `tobj`,
+// replacing the single hardcoded six-vertex quad. Mirrors the learn-wgpu
+// model tutorial's mesh/material split: a `Model` owns one `Mesh` per OBJ
+// submesh plus one `Material` (and bind group) per MTL entry.
+
+use wgpu::util::DeviceExt;
+
+use crate::texture::Texture;
+
+pub trait Vertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static>;
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub tex_coords: [f32; 2],
+    pub normal: [f32; 3],
+}
+
+impl Vertex for ModelVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        use std::mem;
+
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<ModelVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+pub struct Material {
+    pub name: String,
+    pub diffuse_texture: Texture,
+    pub bind_group: wgpu::BindGroup,
+}
+
+pub struct Mesh {
+    pub name: String,
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub num_elements: u32,
+    pub material: usize,
+}
+
+pub struct Model {
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+}
+
+impl Model {
+    /// Loads an OBJ file and its referenced MTL materials from `path`,
+    /// building one diffuse-texture bind group per material using
+    /// `texture_bind_group_layout` (the texture/sampler layout the main
+    /// pipeline uses for group 1).
+    pub fn load(device: &wgpu::Device, queue: &wgpu::Queue, texture_bind_group_layout: &wgpu::BindGroupLayout, path: &str) -> Self {
+        let (models, obj_materials) = tobj::load_obj(
+            path,
+            &(tobj::LoadOptions {
+                triangulate: true,
+                single_index: true,
+                ..Default::default()
+            })
+        ).unwrap_or_else(|e| panic!("Failed to load model {path}: {e}"));
+        let obj_materials = obj_materials.unwrap_or_else(|e| panic!("Failed to load materials for {path}: {e}"));
+
+        let containing_dir = std::path::Path
+            ::new(path)
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new(""));
+
+        let materials = obj_materials
+            .into_iter()
+            .map(|mat| {
+                let diffuse_path = containing_dir.join(&mat.diffuse_texture);
+                let diffuse_texture = Texture::from_path(
+                    device,
+                    queue,
+                    diffuse_path.to_str().unwrap(),
+                    Some(&mat.name)
+                );
+
+                let bind_group = device.create_bind_group(
+                    &(wgpu::BindGroupDescriptor {
+                        layout: texture_bind_group_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+                            },
+                        ],
+                        label: Some(&format!("{} Material Bind Group", mat.name)),
+                    })
+                );
+
+                Material { name: mat.name, diffuse_texture, bind_group }
+            })
+            .collect::<Vec<_>>();
+
+        let meshes = models
+            .into_iter()
+            .map(|m| {
+                let vertices = (0..m.mesh.positions.len() / 3)
+                    .map(|i| ModelVertex {
+                        position: [
+                            m.mesh.positions[i * 3],
+                            m.mesh.positions[i * 3 + 1],
+                            m.mesh.positions[i * 3 + 2],
+                        ],
+                        tex_coords: if m.mesh.texcoords.is_empty() {
+                            [0.0, 0.0]
+                        } else {
+                            [m.mesh.texcoords[i * 2], 1.0 - m.mesh.texcoords[i * 2 + 1]]
+                        },
+                        normal: if m.mesh.normals.is_empty() {
+                            [0.0, 0.0, 0.0]
+                        } else {
+                            [m.mesh.normals[i * 3], m.mesh.normals[i * 3 + 1], m.mesh.normals[i * 3 + 2]]
+                        },
+                    })
+                    .collect::<Vec<_>>();
+
+                let vertex_buffer = device.create_buffer_init(
+                    &(wgpu::util::BufferInitDescriptor {
+                        label: Some(&format!("{} Vertex Buffer", m.name)),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    })
+                );
+                let index_buffer = device.create_buffer_init(
+                    &(wgpu::util::BufferInitDescriptor {
+                        label: Some(&format!("{} Index Buffer", m.name)),
+                        contents: bytemuck::cast_slice(&m.mesh.indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    })
+                );
+
+                Mesh {
+                    name: m.name,
+                    vertex_buffer,
+                    index_buffer,
+                    num_elements: m.mesh.indices.len() as u32,
+                    material: m.mesh.material_id.unwrap_or(0),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Self { meshes, materials }
+    }
+}
+
+pub trait DrawModel<'a> {
+    fn draw_mesh(
+        &mut self,
+        mesh: &'a Mesh,
+        material: &'a Material,
+        camera_bind_group: &'a wgpu::BindGroup,
+        light_bind_group: &'a wgpu::BindGroup,
+        instances: std::ops::Range<u32>
+    );
+    fn draw_model(
+        &mut self,
+        model: &'a Model,
+        camera_bind_group: &'a wgpu::BindGroup,
+        light_bind_group: &'a wgpu::BindGroup,
+        instances: std::ops::Range<u32>
+    );
+}
+
+impl<'a, 'b> DrawModel<'b> for wgpu::RenderPass<'a> where 'b: 'a {
+    fn draw_mesh(
+        &mut self,
+        mesh: &'b Mesh,
+        material: &'b Material,
+        camera_bind_group: &'b wgpu::BindGroup,
+        light_bind_group: &'b wgpu::BindGroup,
+        instances: std::ops::Range<u32>
+    ) {
+        self.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        self.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.set_bind_group(0, camera_bind_group, &[]);
+        self.set_bind_group(1, &material.bind_group, &[]);
+        self.set_bind_group(2, light_bind_group, &[]);
+        self.draw_indexed(0..mesh.num_elements, 0, instances);
+    }
+
+    fn draw_model(
+        &mut self,
+        model: &'b Model,
+        camera_bind_group: &'b wgpu::BindGroup,
+        light_bind_group: &'b wgpu::BindGroup,
+        instances: std::ops::Range<u32>
+    ) {
+        for mesh in &model.meshes {
+            let material = &model.materials[mesh.material];
+            self.draw_mesh(mesh, material, camera_bind_group, light_bind_group, instances.clone());
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// texture_target.rs
+//
+// An offscreen render target for headless rendering, following Ruffle's
+// texture-target approach: a `RENDER_ATTACHMENT | COPY_SRC` texture paired
+// with a `MAP_READ | COPY_DST` readback buffer whose rows are padded to
+// wgpu's 256-byte `bytes_per_row` alignment requirement.
+
+struct BufferDimensions {
+    height: usize,
+    unpadded_bytes_per_row: usize,
+    padded_bytes_per_row: usize,
+}
+
+impl BufferDimensions {
+    fn new(width: usize, height: usize) -> Self {
+        const BYTES_PER_PIXEL: usize = 4;
+
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let unpadded_bytes_per_row = width * BYTES_PER_PIXEL;
+        let padding = (align - (unpadded_bytes_per_row % align)) % align;
+        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+
+        Self { height, unpadded_bytes_per_row, padded_bytes_per_row }
+    }
+}
+
+pub struct TextureTarget {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub size: wgpu::Extent3d,
+    buffer: wgpu::Buffer,
+    dimensions: BufferDimensions,
+}
+
+impl TextureTarget {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(
+            &(wgpu::TextureDescriptor {
+                label: Some("Offscreen Target Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            })
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let dimensions = BufferDimensions::new(width as usize, height as usize);
+        let buffer = device.create_buffer(
+            &(wgpu::BufferDescriptor {
+                label: Some("Offscreen Readback Buffer"),
+                size: (dimensions.padded_bytes_per_row * dimensions.height) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        );
+
+        Self { texture, view, size, buffer, dimensions }
+    }
+
+    /// Copies the render target into the readback buffer. Must be recorded
+    /// after the draw that filled `self.view` and before the encoder is
+    /// submitted.
+    pub fn copy_to_buffer(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.dimensions.padded_bytes_per_row as u32),
+                    rows_per_image: None,
+                },
+            },
+            self.size
+        );
+    }
+
+    /// Maps the readback buffer (populated by a prior `copy_to_buffer` +
+    /// queue submit) and returns its contents as tightly-packed RGBA, with
+    /// the per-row alignment padding stripped out.
+    pub async fn read_rgba(&self, device: &wgpu::Device) -> Vec<u8> {
+        let slice = self.buffer.slice(..);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().expect("Readback buffer mapping callback was dropped").expect("Failed to map readback buffer");
+
+        let mut pixels = Vec::with_capacity(self.dimensions.unpadded_bytes_per_row * self.dimensions.height);
+        for row in slice.get_mapped_range().chunks(self.dimensions.padded_bytes_per_row) {
+            pixels.extend_from_slice(&row[..self.dimensions.unpadded_bytes_per_row]);
+        }
+
+        self.buffer.unmap();
+        pixels
+    }
+}